@@ -1,3 +1,7 @@
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Mutex, Once};
+
 pub mod marks {
     pub const INFO: &str = "ℹ️";
     pub const WARN: &str = "⚠️";
@@ -7,6 +11,294 @@ pub mod marks {
     pub const INPUT: &str = "⌨️";
 }
 
+/// Severity of a leveled log message.
+///
+/// Variants are ordered from least to most severe, so the derived [`Ord`] gives
+/// the total order `Input < Info < Wait < Success < Warn < Error`. Leveled
+/// macros only emit when their severity is at least the process-global
+/// threshold (see [`level`] and [`set_level`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Severity {
+    Input = 0,
+    Info = 1,
+    Wait = 2,
+    Success = 3,
+    Warn = 4,
+    Error = 5,
+}
+
+impl Severity {
+    /// Parses a case-insensitive level name, as accepted in `HAND_LOG`.
+    fn from_name(name: &str) -> Option<Severity> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "input" => Some(Severity::Input),
+            "info" => Some(Severity::Info),
+            "wait" => Some(Severity::Wait),
+            "success" => Some(Severity::Success),
+            "warn" => Some(Severity::Warn),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+
+    /// Recovers a `Severity` from its `#[repr(u8)]` value, clamping any stray
+    /// byte to the least severe variant.
+    fn from_u8(value: u8) -> Severity {
+        match value {
+            1 => Severity::Info,
+            2 => Severity::Wait,
+            3 => Severity::Success,
+            4 => Severity::Warn,
+            5 => Severity::Error,
+            _ => Severity::Input,
+        }
+    }
+}
+
+/// Process-global threshold, stored as the `#[repr(u8)]` of a [`Severity`].
+static LEVEL: AtomicU8 = AtomicU8::new(Severity::Input as u8);
+static INIT: Once = Once::new();
+
+/// Reads `HAND_LOG` into [`LEVEL`] exactly once, on first access.
+fn init() {
+    INIT.call_once(|| {
+        if let Ok(value) = std::env::var("HAND_LOG") {
+            if let Some(severity) = Severity::from_name(&value) {
+                LEVEL.store(severity as u8, Ordering::Relaxed);
+            }
+        }
+    });
+}
+
+/// Returns the current log-level threshold.
+///
+/// On first use this reads the `HAND_LOG` environment variable (e.g.
+/// `HAND_LOG=warn`); if it is unset or unrecognised the threshold defaults to
+/// [`Severity::Input`], so every message is emitted.
+pub fn level() -> Severity {
+    init();
+    Severity::from_u8(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Sets the log-level threshold; messages below `level` are suppressed.
+pub fn set_level(level: Severity) {
+    init();
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Renders a diagnostic in the rustc/cargo style: the `mark` + message head,
+/// optionally carrying a `[code]` suffix, followed by a dimmed
+/// `   --> file:line:col` line listing only the location components supplied.
+///
+/// Used by the `at:`/`line:`/`col:`/`code:` arms of [`error!`] and [`warn!`];
+/// `newline` selects between the `ln` and non-`ln` variants.
+#[doc(hidden)]
+pub fn emit_diag(
+    mark: &str,
+    newline: bool,
+    at: Option<&str>,
+    line: Option<u32>,
+    col: Option<u32>,
+    code: Option<&str>,
+    args: std::fmt::Arguments,
+) {
+    let msg = format!("{}", args);
+    let mut out = match (msg.is_empty(), code) {
+        (true, Some(code)) => format!("{} [{}]", mark, code),
+        (true, None) => mark.to_string(),
+        (false, Some(code)) => format!("{} {} [{}]", mark, msg, code),
+        (false, None) => format!("{} {}", mark, msg),
+    };
+
+    if at.is_some() || line.is_some() || col.is_some() {
+        let mut loc = String::new();
+        if let Some(file) = at {
+            loc.push_str(file);
+        }
+        if let Some(line) = line {
+            if !loc.is_empty() {
+                loc.push(':');
+            }
+            loc.push_str(&line.to_string());
+        }
+        if let Some(col) = col {
+            if !loc.is_empty() {
+                loc.push(':');
+            }
+            loc.push_str(&col.to_string());
+        }
+        out.push('\n');
+        out.push_str(&dim(&format!("   --> {}", loc)));
+    }
+
+    if newline {
+        out.push('\n');
+    }
+
+    write_line(format_args!("{}", out));
+}
+
+/// How output should be colored, overriding the automatic `NO_COLOR`/TTY
+/// detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Always emit color, even when writing to a non-terminal.
+    Always,
+    /// Never emit color, stripping the dim scope brackets and any user color.
+    Never,
+    /// Color only when `NO_COLOR` is unset and stderr is a TTY.
+    Auto,
+}
+
+/// Color override, stored as a discriminant: `0` = [`ColorChoice::Auto`],
+/// `1` = [`ColorChoice::Always`], `2` = [`ColorChoice::Never`].
+#[cfg(feature = "color")]
+static COLOR: AtomicU8 = AtomicU8::new(0);
+
+/// Decides whether color is active right now, honoring an explicit
+/// [`set_color`] override and otherwise `NO_COLOR` plus whether **stderr** —
+/// the stream every macro writes to — is a terminal. The decision is pushed
+/// into `colored` so user-supplied styling in the head strips in lockstep.
+#[cfg(feature = "color")]
+fn should_color() -> bool {
+    let enabled = match COLOR.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal(),
+    };
+    colored::control::set_override(enabled);
+    enabled
+}
+
+/// Overrides color handling process-wide.
+///
+/// [`ColorChoice::Auto`] restores the default behavior, which honors the
+/// `NO_COLOR` environment variable and only colors when stderr is a terminal.
+#[cfg(feature = "color")]
+pub fn set_color(choice: ColorChoice) {
+    COLOR.store(
+        match choice {
+            ColorChoice::Always => 1,
+            ColorChoice::Never => 2,
+            ColorChoice::Auto => 0,
+        },
+        Ordering::Relaxed,
+    );
+    let _ = should_color();
+}
+
+/// No-op override used when the `color` feature is disabled: the `[scope]`
+/// brackets and diagnostic location lines are always plain. Styling already
+/// applied to a user-supplied head is not stripped.
+#[cfg(not(feature = "color"))]
+pub fn set_color(_choice: ColorChoice) {}
+
+/// Renders a dimmed `[scope]` prefix, used by every `scope*` macro.
+///
+/// With the `color` feature enabled this routes through [`colored`], so the
+/// dim brackets are stripped automatically for `NO_COLOR`, non-terminals, and
+/// any [`set_color`] override.
+#[cfg(feature = "color")]
+#[doc(hidden)]
+pub fn scope_prefix(prefix: impl std::fmt::Display) -> impl std::fmt::Display {
+    use colored::Colorize;
+    let bracketed = format!("[{}]", prefix);
+    if should_color() {
+        bracketed.dimmed().to_string()
+    } else {
+        bracketed
+    }
+}
+
+/// Dims a diagnostic fragment when coloring is active, leaving it plain
+/// otherwise. Mirrors the styling of [`scope_prefix`].
+#[cfg(feature = "color")]
+fn dim(text: &str) -> String {
+    use colored::Colorize;
+    if should_color() {
+        text.dimmed().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(not(feature = "color"))]
+fn dim(text: &str) -> String {
+    text.to_string()
+}
+
+/// Global output sink. `None` means the default, `stderr`.
+static SINK: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+/// Whether each emitted line is prefixed with a timestamp. Defaults to `true`
+/// when the `timestamps` feature is enabled, `false` otherwise.
+static TIMESTAMPS: AtomicBool = AtomicBool::new(cfg!(feature = "timestamps"));
+
+/// Redirects all macro output to `writer`, which may be a file, an in-memory
+/// buffer, or any other `Write + Send` target. The default sink is `stderr`.
+pub fn set_writer<W: Write + Send + 'static>(writer: W) {
+    *SINK.lock().unwrap() = Some(Box::new(writer));
+}
+
+/// Enables or disables timestamp prefixing for subsequently emitted lines.
+///
+/// When on, an `HH:MM:SS.mmm` (UTC) stamp is written before the scope prefix,
+/// giving grep-friendly, archivable logs for long-running tools.
+pub fn set_timestamps(on: bool) {
+    TIMESTAMPS.store(on, Ordering::Relaxed);
+}
+
+fn timestamps_enabled() -> bool {
+    TIMESTAMPS.load(Ordering::Relaxed)
+}
+
+/// Formats the current wall-clock time as `HH:MM:SS.mmm` in UTC.
+fn timestamp() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        (secs / 3600) % 24,
+        (secs / 60) % 60,
+        secs % 60,
+        now.subsec_millis()
+    )
+}
+
+/// Funnels one emission through the global sink, prepending a timestamp when
+/// enabled. This is the single write path for `custom!`, `scopecustom!`, and
+/// the diagnostic renderer.
+#[doc(hidden)]
+pub fn write_line(args: std::fmt::Arguments) {
+    let stamp = if timestamps_enabled() {
+        Some(timestamp())
+    } else {
+        None
+    };
+
+    let mut sink = SINK.lock().unwrap();
+    match sink.as_mut() {
+        Some(writer) => {
+            if let Some(stamp) = &stamp {
+                let _ = write!(writer, "{} ", stamp);
+            }
+            let _ = writer.write_fmt(args);
+            let _ = writer.flush();
+        }
+        None => {
+            let stderr = std::io::stderr();
+            let mut handle = stderr.lock();
+            if let Some(stamp) = &stamp {
+                let _ = write!(handle, "{} ", stamp);
+            }
+            let _ = handle.write_fmt(args);
+        }
+    }
+}
+
 /// Prints log message to stderr with a custom head without new line.
 ///
 /// # Examples
@@ -22,7 +314,9 @@ pub mod marks {
 #[cfg(not(test))]
 #[macro_export]
 macro_rules! custom {
-    ($head:expr, $($arg:tt)*) => { eprint!("{} {}", $head, format_args!($($arg)*)) }
+    ($head:expr, $($arg:tt)*) => {
+        $crate::io::write_line(format_args!("{} {}", $head, format_args!($($arg)*)))
+    }
 }
 
 #[cfg(test)]
@@ -56,11 +350,19 @@ macro_rules! customln {
 /// scopecustom!("Fetching", "🌐", "fetching data from {} ... ", "www.example.com"); // [Fetching] 🌐 fetching data from www.example.com ...
 /// scopecustom!("Scanning", "🚨".bright_red().bold(), "{} viruses detected ... ", 3); // [Scanning] 🚨 3 viruses detected ...
 /// ```
-#[cfg(not(test))]
+#[cfg(all(not(test), feature = "color"))]
+#[macro_export]
+macro_rules! scopecustom {
+    ($prefix:expr, $head:expr, $($arg:tt)*) => {
+        $crate::io::write_line(format_args!("{} {} {}", $crate::io::scope_prefix($prefix), $head, format_args!($($arg)*)))
+    }
+}
+
+#[cfg(all(not(test), not(feature = "color")))]
 #[macro_export]
 macro_rules! scopecustom {
     ($prefix:expr, $head:expr, $($arg:tt)*) => {
-        eprint!("\u{1b}[2m[{}]\u{1b}[0m {} {}", $prefix, $head, format_args!($($arg)*))
+        $crate::io::write_line(format_args!("[{}] {} {}", $prefix, $head, format_args!($($arg)*)))
     }
 }
 
@@ -98,7 +400,17 @@ macro_rules! scopecustomln {
 /// info!("testing info ..."); // ℹ testing info ...
 /// info!("testing info for {} users", 4); // ℹ testing info for 4 users
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Info >= $crate::io::level() {
+            custom!($crate::io::marks::INFO, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! info {
     ($($arg:tt)*) => { custom!($crate::io::marks::INFO, $($arg)*) }
 }
@@ -112,7 +424,17 @@ macro_rules! info {
 /// infoln!("Waiting all jobs to complete"); // ℹ Waiting all jobs to complete
 /// infoln!("Next checking will be at {}", "10:00 AM"); // ℹ Next checking will be at 10:00 AM
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! infoln {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Info >= $crate::io::level() {
+            customln!($crate::io::marks::INFO, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! infoln {
     ($($arg:tt)*) => { customln!($crate::io::marks::INFO, $($arg)*) }
 }
@@ -127,7 +449,17 @@ macro_rules! infoln {
 /// scopeinfo!("working hard", "long time no see ..."); // [working hard] ℹ long time no see ...
 /// scopeinfo!("eta: 1 hour", "waiting file `{}` to download ... ", "tlauncher.exe"); // [eta: 1 hour] ℹ waiting file `tlauncher.exe` to download ...
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeinfo {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Info >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::INFO, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeinfo {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::INFO, $($arg)*) }
 }
@@ -142,7 +474,17 @@ macro_rules! scopeinfo {
 /// scopeinfoln!("Building", "Not completed"); // [Building] ℹ Not completed
 /// scopeinfoln!("Dogs", "Dogs are {}", if true { "good" } else { "bad" }); // [Dogs] ℹ Dogs are good
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeinfoln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Info >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::INFO, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeinfoln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::INFO, $($arg)*) }
 }
@@ -156,8 +498,26 @@ macro_rules! scopeinfoln {
 ///
 /// warn!("test warn"); // ⚠ test warn
 /// warn!("no more warnings {}", "i said"); // ⚠ no more warnings i said
+///
+/// // optional source location and diagnostic code, in the cargo style:
+/// warn!(at: "main.rs", line: 10, "deprecated call"); // ⚠ deprecated call
+///                                                    //    --> main.rs:10
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! warn {
+    (at: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, false, None, None, None, None, at: $($rest)*) };
+    (line: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, false, None, None, None, None, line: $($rest)*) };
+    (col: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, false, None, None, None, None, col: $($rest)*) };
+    (code: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, false, None, None, None, None, code: $($rest)*) };
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Warn >= $crate::io::level() {
+            custom!($crate::io::marks::WARN, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! warn {
     ($($arg:tt)*) => { custom!($crate::io::marks::WARN, $($arg)*) }
 }
@@ -172,7 +532,21 @@ macro_rules! warn {
 /// warnln!("You have not weared a mask"); // ⚠ You have not weared a mask
 /// warnln!("You have not sent {} dollars to you mom", 1000); // ⚠ You have not sent 1000 dollars to you mom
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! warnln {
+    (at: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, true, None, None, None, None, at: $($rest)*) };
+    (line: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, true, None, None, None, None, line: $($rest)*) };
+    (col: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, true, None, None, None, None, col: $($rest)*) };
+    (code: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::WARN, $crate::io::Severity::Warn, true, None, None, None, None, code: $($rest)*) };
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Warn >= $crate::io::level() {
+            customln!($crate::io::marks::WARN, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! warnln {
     ($($arg:tt)*) => { customln!($crate::io::marks::WARN, $($arg)*) }
 }
@@ -187,7 +561,17 @@ macro_rules! warnln {
 /// scopewarn!("driving", "computing the distance ... "); // [driving] ⚠ computing the distance ...
 /// scopewarn!("fixing", "fixing the problem ... "); // [fixing] ⚠ fixing the problem ...
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopewarn {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Warn >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::WARN, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopewarn {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::WARN, $($arg)*) }
 }
@@ -201,7 +585,17 @@ macro_rules! scopewarn {
 /// scopewarnln!("car", "the problem fixed"); // [car] ⚠ the problem fixed
 /// scopewarnln!("boilerplate", "your code has too many boilerplate"); // [boilerplate] ⚠ your code has too many boilerplate
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopewarnln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Warn >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::WARN, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopewarnln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::WARN, $($arg)*) }
 }
@@ -216,7 +610,17 @@ macro_rules! scopewarnln {
 /// success!("Operation successful"); // ✔ Operation successful
 /// success!("Data processed"); // ✔ Data processed
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! success {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Success >= $crate::io::level() {
+            custom!($crate::io::marks::SUCCESS, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! success {
     ($($arg:tt)*) => { custom!($crate::io::marks::SUCCESS, $($arg)*) }
 }
@@ -231,7 +635,17 @@ macro_rules! success {
 /// successln!("Task completed"); // ✔ Task completed
 /// successln!("Process finished"); // ✔ Process finished
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! successln {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Success >= $crate::io::level() {
+            customln!($crate::io::marks::SUCCESS, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! successln {
     ($($arg:tt)*) => { customln!($crate::io::marks::SUCCESS, $($arg)*) }
 }
@@ -247,7 +661,17 @@ macro_rules! successln {
 /// waitln!("Reboot in 3 seconds");
 /// // [Installing] ✔ Completed in 9 secs. ⌛ Reboot in 3 seconds
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopesuccess {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Success >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::SUCCESS, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopesuccess {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::SUCCESS, $($arg)*) }
 }
@@ -262,11 +686,62 @@ macro_rules! scopesuccess {
 /// scopesuccessln!("Deploy", "Finished in {} secs.", 9); // [Deploy] ✔ Finished in 9 secs.
 /// scopesuccessln!("Cleaning up", "Finished"); // [Cleaning up] ✔ Finished
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopesuccessln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Success >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::SUCCESS, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopesuccessln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::SUCCESS, $($arg)*) }
 }
 
+/// Internal muncher backing the `at:`/`line:`/`col:`/`code:` diagnostic arms of
+/// [`error!`] and [`warn!`]. It peels the optional key fragments off the front
+/// in any order, accumulating them as `Option`s, then hands the rest to
+/// [`crate::io::emit_diag`] as the format arguments.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __handdiag {
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, at: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, Some($v), $line, $col, $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, line: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, Some($v), $col, $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, col: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, Some($v), $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, code: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, $col, Some($v), $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, at: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, Some($v), $line, $col, $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, line: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, Some($v), $col, $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, col: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, Some($v), $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, code: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, $col, Some($v),)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr,) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, $col, $code, "")
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, $($arg:tt)*) => {
+        if $sev >= $crate::io::level() {
+            $crate::io::emit_diag($mark, $nl, $at, $line, $col, $code, format_args!($($arg)*))
+        }
+    };
+}
+
 /// Prints a log message to stderr with a new line, with a specified prefix.
 ///
 /// # Examples
@@ -276,8 +751,65 @@ macro_rules! scopesuccessln {
 ///
 /// error!("An error occurred"); // ❌ An error occurred
 /// error!("Invalid input"); // ❌ Invalid input
+///
+/// // optional source location and diagnostic code, in the cargo style:
+/// error!(at: "config.rs", line: 2, col: 3, code: "E1234", "unexpected token");
+/// // ❌ unexpected token [E1234]
+/// //    --> config.rs:2:3
 /// ```
+/// Internal muncher backing the `at:`/`line:`/`col:`/`code:` diagnostic arms of
+/// [`error!`] and [`warn!`]. It peels the optional key fragments off the front
+/// in any order, accumulating them as `Option`s, then hands the rest to
+/// [`crate::io::emit_diag`] as the format arguments.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __handdiag {
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, at: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, Some($v), $line, $col, $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, line: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, Some($v), $col, $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, col: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, Some($v), $code, $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, code: $v:expr, $($rest:tt)*) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, $col, Some($v), $($rest)*)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, at: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, Some($v), $line, $col, $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, line: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, Some($v), $col, $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, col: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, Some($v), $code,)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, code: $v:expr) => {
+        $crate::__handdiag!($mark, $sev, $nl, $at, $line, $col, Some($v),)
+    };
+    ($mark:expr, $sev:expr, $nl:expr, $at:expr, $line:expr, $col:expr, $code:expr, $($arg:tt)*) => {
+        if $sev >= $crate::io::level() {
+            $crate::io::emit_diag($mark, $nl, $at, $line, $col, $code, format_args!($($arg)*))
+        }
+    };
+}
+
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! error {
+    (at: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, false, None, None, None, None, at: $($rest)*) };
+    (line: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, false, None, None, None, None, line: $($rest)*) };
+    (col: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, false, None, None, None, None, col: $($rest)*) };
+    (code: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, false, None, None, None, None, code: $($rest)*) };
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Error >= $crate::io::level() {
+            custom!($crate::io::marks::ERROR, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! error {
     ($($arg:tt)*) => { custom!($crate::io::marks::ERROR, $($arg)*) }
 }
@@ -292,7 +824,21 @@ macro_rules! error {
 /// errorln!("Critical error: {} {} seconds", "your pc will die in", 3); // ❌ Critical error your pc will die in 3 seconds
 /// errorln!("Fatal error occurred"); // ❌ Fatal error occurred
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! errorln {
+    (at: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, true, None, None, None, None, at: $($rest)*) };
+    (line: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, true, None, None, None, None, line: $($rest)*) };
+    (col: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, true, None, None, None, None, col: $($rest)*) };
+    (code: $($rest:tt)*) => { $crate::__handdiag!($crate::io::marks::ERROR, $crate::io::Severity::Error, true, None, None, None, None, code: $($rest)*) };
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Error >= $crate::io::level() {
+            customln!($crate::io::marks::ERROR, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! errorln {
     ($($arg:tt)*) => { customln!($crate::io::marks::ERROR, $($arg)*) }
 }
@@ -308,7 +854,17 @@ macro_rules! errorln {
 /// successln!("Retrying successful")
 /// // [github.com] ❌ Unable to fetch. Retrying ... ✅ Retrying successful
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeerror {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Error >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::ERROR, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeerror {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::ERROR, $($arg)*) }
 }
@@ -323,7 +879,17 @@ macro_rules! scopeerror {
 /// scopeerrorln!("FATAL", "Your GPU died"); // [FATAL] ❌ Your GPU died
 /// scopeerrorln!("FATAL", "Your motherboard blow up"); // [FATAL] ❌ Your motherboard blow up
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeerrorln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Error >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::ERROR, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeerrorln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::ERROR, $($arg)*) }
 }
@@ -338,7 +904,17 @@ macro_rules! scopeerrorln {
 /// wait!("Waiting for input"); // ⌛ Waiting for input
 /// wait!("Processing data"); // ⌛ Processing data
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! wait {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Wait >= $crate::io::level() {
+            custom!($crate::io::marks::WAIT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! wait {
     ($($arg:tt)*) => { custom!($crate::io::marks::WAIT, $($arg)*) }
 }
@@ -353,7 +929,17 @@ macro_rules! wait {
 /// waitln!("This operation can take a while"); // ⌛ This operation can take a while
 /// waitln!("Fetching results"); // ⌛ Fetching results
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! waitln {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Wait >= $crate::io::level() {
+            customln!($crate::io::marks::WAIT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! waitln {
     ($($arg:tt)*) => { customln!($crate::io::marks::WAIT, $($arg)*) }
 }
@@ -369,7 +955,17 @@ macro_rules! waitln {
 /// successln!("done in {} secs", 13.578);
 /// // [reading config] ⌛ reading config ... ✅ done in 13.578 secs
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopewait {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Wait >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::WAIT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopewait {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::WAIT, $($arg)*) }
 }
@@ -384,7 +980,17 @@ macro_rules! scopewait {
 /// scopewaitln!("Documenting", "Wait until Give me an Oscar will be done"); // [Documenting] ⌛ Wait until Give me an Oscar will be done
 /// scopewaitln!("Testing", "Wait for the test to be done"); // [Testing] ⌛ Wait for the test to be done
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopewaitln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Wait >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::WAIT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopewaitln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::WAIT, $($arg)*) }
 }
@@ -401,7 +1007,17 @@ macro_rules! scopewaitln {
 /// input!("enter the folder path\n> "); // ⌨️ enter the folder path
 ///                                      // >
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! input {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Input >= $crate::io::level() {
+            custom!($crate::io::marks::INPUT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! input {
     ($($arg:tt)*) => { custom!($crate::io::marks::INPUT, $($arg)*) }
 }
@@ -416,7 +1032,17 @@ macro_rules! input {
 /// inputln!("Enter your age"); // ⌨️ Enter your age
 /// inputln!("How many cores to use? > "); // ⌨️ How many cores to use? >
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! inputln {
+    ($($arg:tt)*) => {
+        if $crate::io::Severity::Input >= $crate::io::level() {
+            customln!($crate::io::marks::INPUT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! inputln {
     ($($arg:tt)*) => { customln!($crate::io::marks::INPUT, $($arg)*) }
 }
@@ -431,7 +1057,17 @@ macro_rules! inputln {
 /// scopeinput!("Authentication", "Password: "); // [Authentication] ⌨️ Password:
 /// scopesuccessln!("Authentication", "Successfully logged in"); // [Authentication] ✅ Successfully logged in
 /// ```
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeinput {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Input >= $crate::io::level() {
+            scopecustom!($prefix, $crate::io::marks::INPUT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeinput {
     ($prefix:expr, $($arg:tt)*) => { scopecustom!($prefix, $crate::io::marks::INPUT, $($arg)*) }
 }
@@ -441,11 +1077,154 @@ macro_rules! scopeinput {
 /// # Examples
 ///
 /// **¯\\_(ツ)_/¯**
+#[cfg(not(test))]
 #[macro_export]
+macro_rules! scopeinputln {
+    ($prefix:expr, $($arg:tt)*) => {
+        if $crate::io::Severity::Input >= $crate::io::level() {
+            scopecustomln!($prefix, $crate::io::marks::INPUT, $($arg)*)
+        }
+    }
+}
+
+#[cfg(test)]
 macro_rules! scopeinputln {
     ($prefix:expr, $($arg:tt)*) => { scopecustomln!($prefix, $crate::io::marks::INPUT, $($arg)*) }
 }
 
+/// Logs an `info` line on the `Err` branch and forwards the `Result`
+/// unchanged, so it drops straight into `?` chains.
+///
+/// The `Display` of the contained error is appended after the user message,
+/// separated by `: `. The `Ok` branch is left untouched and costs nothing.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// let parsed = info_err!("12".parse::<u32>(), "could not parse count")?; // 12
+/// # Ok::<(), std::num::ParseIntError>(())
+/// ```
+#[macro_export]
+macro_rules! info_err {
+    ($res:expr, $($arg:tt)*) => {{
+        let res = $res;
+        if let Err(ref e) = res {
+            infoln!("{}: {}", format_args!($($arg)*), e);
+        }
+        res
+    }}
+}
+
+/// Like [`info_err!`], but discards the `Result` after logging instead of
+/// forwarding it.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// info_err_omit!("oops".parse::<u32>(), "ignored parse failure"); // ℹ ignored parse failure: ...
+/// ```
+#[macro_export]
+macro_rules! info_err_omit {
+    ($res:expr, $($arg:tt)*) => {{
+        if let Err(ref e) = $res {
+            infoln!("{}: {}", format_args!($($arg)*), e);
+        }
+    }}
+}
+
+/// Logs a `warn` line on the `Err` branch and forwards the `Result`
+/// unchanged, so it drops straight into `?` chains.
+///
+/// The `Display` of the contained error is appended after the user message,
+/// separated by `: `.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// let path = "config.toml";
+/// let contents = warn_err!(std::fs::read_to_string(path), "failed to load {}", path);
+/// // on Err: ⚠️ failed to load config.toml: <e>
+/// ```
+#[macro_export]
+macro_rules! warn_err {
+    ($res:expr, $($arg:tt)*) => {{
+        let res = $res;
+        if let Err(ref e) = res {
+            warnln!("{}: {}", format_args!($($arg)*), e);
+        }
+        res
+    }}
+}
+
+/// Like [`warn_err!`], but discards the `Result` after logging instead of
+/// forwarding it.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// warn_err_omit!(std::fs::remove_file("tmp"), "could not remove {}", "tmp");
+/// ```
+#[macro_export]
+macro_rules! warn_err_omit {
+    ($res:expr, $($arg:tt)*) => {{
+        if let Err(ref e) = $res {
+            warnln!("{}: {}", format_args!($($arg)*), e);
+        }
+    }}
+}
+
+/// Logs an `error` line on the `Err` branch and forwards the `Result`
+/// unchanged, so it drops straight into `?` chains.
+///
+/// The `Display` of the contained error is appended after the user message,
+/// separated by `: `.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// let contents = error_err!(std::fs::read_to_string("config.toml"), "failed to load config");
+/// // on Err: ❌ failed to load config: <e>
+/// ```
+#[macro_export]
+macro_rules! error_err {
+    ($res:expr, $($arg:tt)*) => {{
+        let res = $res;
+        if let Err(ref e) = res {
+            errorln!("{}: {}", format_args!($($arg)*), e);
+        }
+        res
+    }}
+}
+
+/// Like [`error_err!`], but discards the `Result` after logging instead of
+/// forwarding it.
+///
+/// # Examples
+///
+/// ```
+/// use hand::*;
+///
+/// error_err_omit!(std::fs::read_to_string("config.toml"), "failed to load config");
+/// ```
+#[macro_export]
+macro_rules! error_err_omit {
+    ($res:expr, $($arg:tt)*) => {{
+        if let Err(ref e) = $res {
+            errorln!("{}: {}", format_args!($($arg)*), e);
+        }
+    }}
+}
+
 #[cfg(test)]
 mod tests {
     use crate::*;
@@ -585,4 +1364,91 @@ mod tests {
             format!("\u{1b}[2m[prewaitln]\u{1b}[0m \u{1b}[1;35m⌛\u{1b}[0m some formatting 123\n")
         );
     }
+
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Test sink that keeps the bytes written through [`set_writer`] readable.
+    #[derive(Clone)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn severity_order_and_parsing() {
+        use super::Severity::*;
+        assert!(Input < Info && Info < Wait && Wait < Success && Success < Warn && Warn < Error);
+        assert_eq!(super::Severity::from_name("WARN"), Some(Warn));
+        assert_eq!(super::Severity::from_name("  info "), Some(Info));
+        assert_eq!(super::Severity::from_name("nonsense"), None);
+        assert_eq!(super::Severity::from_u8(5), Error);
+        assert_eq!(super::Severity::from_u8(200), Input);
+    }
+
+    #[test]
+    fn timestamp_shape() {
+        let stamp = super::timestamp();
+        assert_eq!(stamp.len(), 12);
+        let bytes = stamp.as_bytes();
+        assert_eq!(bytes[2], b':');
+        assert_eq!(bytes[5], b':');
+        assert_eq!(bytes[8], b'.');
+    }
+
+    #[test]
+    fn err_macros_forward_and_omit() {
+        assert_eq!(info_err!(Ok::<u32, String>(7), "ctx"), Ok(7));
+        assert_eq!(
+            warn_err!(Err::<u32, String>("boom".into()), "load {}", "cfg"),
+            Err("boom".to_string())
+        );
+        assert_eq!(error_err!(Ok::<u32, String>(1), "ok"), Ok(1));
+        info_err_omit!(Err::<u32, String>("x".into()), "dropped");
+    }
+
+    #[test]
+    fn sink_and_diag_rendering() {
+        super::set_timestamps(false);
+        #[cfg(feature = "color")]
+        super::set_color(super::ColorChoice::Never);
+
+        let buf = SharedBuf(Arc::new(Mutex::new(Vec::new())));
+        super::set_writer(buf.clone());
+
+        super::emit_diag(
+            marks::ERROR,
+            false,
+            Some("config.rs"),
+            Some(2),
+            Some(3),
+            Some("E1234"),
+            format_args!("unexpected token"),
+        );
+        assert_eq!(
+            String::from_utf8(buf.0.lock().unwrap().clone()).unwrap(),
+            format!("{} unexpected token [E1234]\n   --> config.rs:2:3", marks::ERROR)
+        );
+
+        buf.0.lock().unwrap().clear();
+        super::emit_diag(marks::ERROR, false, None, None, None, Some("E1"), format_args!(""));
+        assert_eq!(
+            String::from_utf8(buf.0.lock().unwrap().clone()).unwrap(),
+            format!("{} [E1]", marks::ERROR)
+        );
+
+        buf.0.lock().unwrap().clear();
+        super::emit_diag(marks::WARN, false, Some("f.rs"), Some(9), None, None, format_args!("oops"));
+        assert_eq!(
+            String::from_utf8(buf.0.lock().unwrap().clone()).unwrap(),
+            format!("{} oops\n   --> f.rs:9", marks::WARN)
+        );
+    }
 }
\ No newline at end of file